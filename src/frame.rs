@@ -0,0 +1,148 @@
+//! **frame** is an offscreen `Surface` backend: it rasterizes a plot into an in-memory
+//! pixel buffer instead of driving an SDL window, so it can be written out as a PNG.
+//!
+//! Users of **dataplotlib** should not need to access **frame** directly; see
+//! `Plotter::save2d`.
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use png;
+
+use plot::{PrimitiveSink, Surface, render_plot};
+use layer::{Layer, default_layers};
+
+/// An in-memory ARGB8888 pixel buffer that a plot can be rasterized into.
+pub struct Frame {
+    pub width: u32,
+    pub height: u32,
+    pub bitmap: Vec<u32>,
+}
+
+impl Frame {
+    pub fn new(width: u32, height: u32) -> Frame {
+        Frame { width, height, bitmap: vec![0xFFFFFFFFu32; (width * height) as usize] }
+    }
+
+    pub fn set_pixel(&mut self, x: i32, y: i32, argb: u32) {
+        if x < 0 || y < 0 || x as u32 >= self.width || y as u32 >= self.height {
+            return;
+        }
+        let idx = y as usize * self.width as usize + x as usize;
+        self.bitmap[idx] = argb;
+    }
+
+    // Bresenham's line algorithm, mirroring the `thick_line` calls `Window::draw` makes
+    // against the SDL renderer.
+    fn line(&mut self, x1: i16, y1: i16, x2: i16, y2: i16, argb: u32) {
+        let (mut x1, mut y1, x2, y2) = (x1 as i32, y1 as i32, x2 as i32, y2 as i32);
+        let dx = (x2 - x1).abs();
+        let dy = -(y2 - y1).abs();
+        let sx = if x1 < x2 { 1 } else { -1 };
+        let sy = if y1 < y2 { 1 } else { -1 };
+        let mut err = dx + dy;
+
+        loop {
+            self.set_pixel(x1, y1, argb);
+            if x1 == x2 && y1 == y2 {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x1 += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y1 += sy;
+            }
+        }
+    }
+
+    fn thick_line_px(&mut self, x1: i16, y1: i16, x2: i16, y2: i16, width: u8, argb: u32) {
+        let half = (width as i32) / 2;
+
+        // Offset each stroke perpendicular to the segment rather than always along y, so
+        // vertical (and near-vertical) segments get the same extra width as horizontal ones.
+        let (dx, dy) = ((x2 - x1) as f64, (y2 - y1) as f64);
+        let len = (dx * dx + dy * dy).sqrt();
+        let (perp_x, perp_y) = if len > 0.0 { (-dy / len, dx / len) } else { (1.0, 0.0) };
+
+        for offset in -half..=half {
+            let ox = (perp_x * offset as f64).round() as i16;
+            let oy = (perp_y * offset as f64).round() as i16;
+            self.line(x1 + ox, y1 + oy, x2 + ox, y2 + oy, argb);
+        }
+    }
+
+    // Rectangle outline, mirroring the `rectangle` calls `Window::draw` makes.
+    fn rect_px(&mut self, x0: i16, y0: i16, x1: i16, y1: i16, argb: u32) {
+        self.line(x0, y0, x1, y0, argb);
+        self.line(x1, y0, x1, y1, argb);
+        self.line(x1, y1, x0, y1, argb);
+        self.line(x0, y1, x0, y0, argb);
+    }
+
+    /// Writes the buffer out as a PNG file at `path`.
+    pub fn save_png(&self, path: &Path) -> Result<(), String> {
+        let file = File::create(path).map_err(|e| e.to_string())?;
+        let writer = BufWriter::new(file);
+
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::RGBA);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+
+        let mut data = Vec::with_capacity(self.bitmap.len() * 4);
+        for &argb in &self.bitmap {
+            data.push(((argb >> 16) & 0xFF) as u8);
+            data.push(((argb >> 8) & 0xFF) as u8);
+            data.push((argb & 0xFF) as u8);
+            data.push(((argb >> 24) & 0xFF) as u8);
+        }
+        writer.write_image_data(&data).map_err(|e| e.to_string())
+    }
+}
+
+fn f32_4_to_argb(col: [f32; 4]) -> u32 {
+    let a = (col[3] * 255f32) as u32;
+    let r = (col[0] * 255f32) as u32;
+    let g = (col[1] * 255f32) as u32;
+    let b = (col[2] * 255f32) as u32;
+    (a << 24) | (r << 16) | (g << 8) | b
+}
+
+impl PrimitiveSink for Frame {
+    fn size(&self) -> (u32, u32) {
+        (self.width, self.height)
+    }
+
+    fn clear(&mut self, color: [f32; 4]) {
+        let argb = f32_4_to_argb(color);
+        for px in self.bitmap.iter_mut() {
+            *px = argb;
+        }
+    }
+
+    fn thick_line(&mut self, x1: i16, y1: i16, x2: i16, y2: i16, width: u8, color: [f32; 4]) {
+        self.thick_line_px(x1, y1, x2, y2, width, f32_4_to_argb(color));
+    }
+
+    fn rect(&mut self, x0: i16, y0: i16, x1: i16, y1: i16, color: [f32; 4]) {
+        self.rect_px(x0, y0, x1, y1, f32_4_to_argb(color));
+    }
+
+    fn present(&mut self) {
+        // Nothing to present to; the caller reads the buffer back via `save_png`.
+    }
+}
+
+impl Surface for Frame {
+    fn draw_plots(&mut self, xs: Vec<Vec<f64>>, ys: Vec<Vec<f64>>, colors: Vec<[f32; 4]>, plot_bounds: [f64; 4],
+                  custom_layers: Vec<Box<Layer>>) {
+        let mut layers = default_layers();
+        layers.extend(custom_layers);
+        render_plot(self, &xs, &ys, &colors, plot_bounds, &layers);
+    }
+}
@@ -0,0 +1,145 @@
+//! **layer** defines the ordered render stack `Window::draw` composites through.
+//!
+//! `Window::draw` used to be one monolithic function that cleared, drew the border, drew the
+//! zero axis, then looped over series. That made it hard to add gridlines, legends or
+//! annotations without tangling them together. It's now a stack of `Layer` trait objects,
+//! each given a read-only `DrawCtx` and a `PrimitiveSink` to draw into, composited in z-order
+//! from `BackgroundLayer` at the bottom up through any overlays registered via
+//! `PlotBuilder2D::add_layer`.
+
+use std::cmp::min;
+
+use plot::{PrimitiveSink, point2plot};
+
+/// The read-only state a `Layer` needs to render itself: the series data, the rectangle
+/// currently visible through the camera, and the pixel dimensions being drawn into.
+pub struct DrawCtx<'a> {
+    pub xs: &'a Vec<Vec<f64>>,
+    pub ys: &'a Vec<Vec<f64>>,
+    pub colors: &'a Vec<[f32; 4]>,
+    pub view_bounds: [f64; 4],
+    pub size: (u32, u32),
+}
+
+impl<'a> DrawCtx<'a> {
+    // The margin geometry every layer maps data/pixel coordinates through: `space` is the pixel
+    // coordinate of the plot area's near edge and `m` that of its far edge, so the usable plot
+    // edge length is `m - space`.
+    pub fn geometry(&self) -> (f64, f64) {
+        let margin = 0.05;
+        let m = min(self.size.0, self.size.1) as f64;
+        let space = m * margin;
+        (m * (1.0 - margin), space)
+    }
+}
+
+/// A single ordered stage of the render stack. `Window` composites its `layers` in z-order
+/// each time it redraws; custom overlays (tick marks, annotations, ...) implement this and are
+/// registered via `PlotBuilder2D::add_layer`, drawn on top of the defaults.
+pub trait Layer {
+    fn render(&self, sink: &mut PrimitiveSink, ctx: &DrawCtx);
+}
+
+/// Clears the canvas and draws the double-rectangle border, as `Window::draw` always did
+/// before any series or axis is drawn.
+pub(crate) struct BackgroundLayer;
+
+impl Layer for BackgroundLayer {
+    fn render(&self, sink: &mut PrimitiveSink, ctx: &DrawCtx) {
+        let bordercol = [0.95, 0.95, 0.95, 1.0];
+        let bgcol = [1.0, 1.0, 1.0, 1.0];
+        let (m, space) = ctx.geometry();
+
+        sink.clear(bordercol);
+        sink.rect((space - 1.0) as i16, (space - 1.0) as i16, (m - 1.0) as i16, (m - 1.0) as i16, [0.0, 0.0, 1.0, 1.0]);
+        sink.rect((space + 1.0) as i16, (space + 1.0) as i16, (m + 1.0) as i16, (m + 1.0) as i16, bgcol);
+    }
+}
+
+/// Draws `divisions - 1` evenly spaced horizontal and vertical gridlines across the plot area.
+pub(crate) struct GridLayer {
+    pub divisions: u32,
+}
+
+impl Layer for GridLayer {
+    fn render(&self, sink: &mut PrimitiveSink, ctx: &DrawCtx) {
+        let gridcol = [0.85, 0.85, 0.85, 1.0];
+        let (m, space) = ctx.geometry();
+
+        for i in 1..self.divisions {
+            let t = (space + (m - space) * (i as f64 / self.divisions as f64)) as i16;
+            sink.thick_line(t, space as i16, t, m as i16, 1, gridcol);
+            sink.thick_line(space as i16, t, m as i16, t, 1, gridcol);
+        }
+    }
+}
+
+/// Draws the zero axis line, at the same `y = 0` crossing `Window::draw` always rendered.
+pub(crate) struct AxisLayer;
+
+impl Layer for AxisLayer {
+    fn render(&self, sink: &mut PrimitiveSink, ctx: &DrawCtx) {
+        let axiscol = [0.0, 0.0, 0.0, 1.0];
+        let (m, space) = ctx.geometry();
+        let y_max = ctx.view_bounds[1];
+        let y_min = ctx.view_bounds[3];
+
+        let y0 = (m + space) as i16 - point2plot(0.0, y_min, y_max, m, space);
+        sink.thick_line(space as i16, y0, m as i16, y0, 2, axiscol);
+    }
+}
+
+/// Draws each series' polyline, mapped through `point2plot` exactly as `Window::draw` always did.
+pub(crate) struct SeriesLayer;
+
+impl Layer for SeriesLayer {
+    fn render(&self, sink: &mut PrimitiveSink, ctx: &DrawCtx) {
+        let (m, space) = ctx.geometry();
+        let x_max = ctx.view_bounds[0];
+        let y_max = ctx.view_bounds[1];
+        let x_min = ctx.view_bounds[2];
+        let y_min = ctx.view_bounds[3];
+
+        for i in 0..ctx.colors.len() {
+            let color = ctx.colors[i];
+
+            let y_inv = (m + space) as i16;
+            let yt: Vec<i16> = ctx.ys[i].iter().map(|y| y_inv - point2plot(*y, y_min, y_max, m, space)).collect();
+            let xt: Vec<i16> = ctx.xs[i].iter().map(|x| point2plot(*x, x_min, x_max, m, space)).collect();
+
+            let len = ctx.xs[i].len();
+            for j in 0..len - 1 {
+                let (xa, ya) = (xt[j + 0], yt[j + 0]);
+                let (xb, yb) = (xt[j + 1], yt[j + 1]);
+                sink.thick_line(xa, ya, xb, yb, 2, color);
+            }
+        }
+    }
+}
+
+/// Draws one small color swatch per series in the top-right corner, in series order.
+pub(crate) struct LegendLayer;
+
+impl Layer for LegendLayer {
+    fn render(&self, sink: &mut PrimitiveSink, ctx: &DrawCtx) {
+        let (m, space) = ctx.geometry();
+        let swatch = 10.0;
+        let pad = 4.0;
+
+        for (i, &color) in ctx.colors.iter().enumerate() {
+            let x0 = m - swatch - pad;
+            let y0 = space + pad + i as f64 * (swatch + pad);
+            sink.rect(x0 as i16, y0 as i16, (x0 + swatch) as i16, (y0 + swatch) as i16, color);
+        }
+    }
+}
+
+/// The render stack every `Window` draws: background, grid, axis, series, then the legend
+/// on top.
+pub(crate) fn default_layers() -> Vec<Box<Layer>> {
+    vec![Box::new(BackgroundLayer),
+         Box::new(GridLayer{divisions: 4}),
+         Box::new(AxisLayer),
+         Box::new(SeriesLayer),
+         Box::new(LegendLayer)]
+}
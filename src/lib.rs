@@ -0,0 +1,21 @@
+//! **dataplotlib** is a simple 2D plotting library.
+//!
+//! `Plotter` is the entry point: `plot2d` opens a live, interactive SDL window, `save2d`
+//! renders a plot offscreen to a PNG, and `plot2d_term` renders one to stdout as a terminal
+//! character grid.
+
+extern crate sdl2;
+extern crate png;
+
+mod plotbuilder;
+mod util;
+mod plot;
+mod plotter;
+mod frame;
+mod term;
+mod layer;
+
+pub use plotter::Plotter;
+pub use plotbuilder::{PlotBuilder2D, PlotVals2D};
+pub use layer::{Layer, DrawCtx};
+pub use plot::PrimitiveSink;
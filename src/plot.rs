@@ -13,7 +13,7 @@ use sdl2::rect::Point;
 use sdl2::render::Renderer;
 
 use std::cmp::min;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::{mem, thread, f64};
 use std::sync::Mutex;
 use std::sync::Arc;
@@ -21,19 +21,24 @@ use std::collections::HashMap;
 use std::collections::VecDeque;
 
 use plotbuilder::*;
+use layer::{Layer, DrawCtx, default_layers};
 
 
 pub struct Plot {
 }
 
 pub enum GUITask {
-    PlotTask(PlotBuilder2D, Plot),
+    PlotTask(u32, PlotBuilder2D, Plot),
+    UpdateTask(u32, Vec<PlotVals2D>),
     Terminate,
 }
 
 pub struct PlotGUI<'a> {
     sdl_context: sdl2::Sdl,
     pub windows: HashMap<u32, Window<'a>>,
+    // Maps the caller-facing window handle returned by `Plotter::plot2d` to the SDL window id,
+    // since the SDL id isn't known until the window is actually created on the GUI thread.
+    handles: HashMap<u32, u32>,
 }
 
 pub struct Window<'a>{
@@ -42,11 +47,65 @@ pub struct Window<'a>{
     xs: Option<Vec<Vec<f64>>>,
     ys: Option<Vec<Vec<f64>>>,
     colors: Option<Vec<[f32; 4]>>,
+    // The auto-fit bounds computed by `get_plot_bounds`; the camera is applied on top of
+    // these to derive the rectangle that's actually visible.
     plot_bounds: Option<[f64; 4]>,
+    camera: Camera2d,
+    last_mouse_pos: (i32, i32),
+    // Set whenever the data, camera or size changed; cleared once `draw` has re-rasterized.
+    dirty: bool,
+    // The ordered render stack `draw` composites; see `layer::default_layers`.
+    layers: Vec<Box<Layer>>,
 }
 
-trait Surface {
-    fn draw_plots(&mut self, xs: Vec<Vec<f64>>, ys: Vec<Vec<f64>>, colors: Vec<[f32; 4]>, plot_bounds: [f64; 4]);
+/// `Camera2d` is the pan/zoom state of a `Window`: `center` is the data-space point shown in
+/// the middle of the plot, and `scale` shrinks (>1) or grows (<1) the auto-fit bounds around
+/// it. Both default to showing the auto-fit bounds exactly, via `Camera2d::from_bounds`.
+#[derive(Clone, Copy)]
+struct Camera2d {
+    center: (f64, f64),
+    scale: (f64, f64),
+}
+
+impl Camera2d {
+    fn from_bounds(plot_bounds: [f64; 4]) -> Camera2d {
+        let x_max = plot_bounds[0];
+        let y_max = plot_bounds[1];
+        let x_min = plot_bounds[2];
+        let y_min = plot_bounds[3];
+
+        Camera2d {
+            center: ((x_max + x_min) / 2.0, (y_max + y_min) / 2.0),
+            scale: (1.0, 1.0),
+        }
+    }
+
+    // Derives the visible [max_x, max_y, min_x, min_y] rectangle from the auto-fit bounds by
+    // recentering on `center` and scaling the half-extents by `scale`.
+    fn view_bounds(&self, auto_bounds: [f64; 4]) -> [f64; 4] {
+        let half_w = (auto_bounds[0] - auto_bounds[2]) / 2.0 / self.scale.0;
+        let half_h = (auto_bounds[1] - auto_bounds[3]) / 2.0 / self.scale.1;
+
+        [self.center.0 + half_w, self.center.1 + half_h, self.center.0 - half_w, self.center.1 - half_h]
+    }
+}
+
+pub(crate) trait Surface {
+    fn draw_plots(&mut self, xs: Vec<Vec<f64>>, ys: Vec<Vec<f64>>, colors: Vec<[f32; 4]>, plot_bounds: [f64; 4],
+                  custom_layers: Vec<Box<Layer>>);
+}
+
+/// `PrimitiveSink` is the small set of drawing primitives a `draw_plots` call is built from.
+/// Any backend that can clear itself, draw a thick line and an outlined rectangle can drive
+/// `render_plot`, which is what lets `Window` (SDL) and `Frame` (in-memory buffer) share one
+/// geometry implementation instead of duplicating the `point2plot` mapping twice. Public so a
+/// custom `Layer` registered via `PlotBuilder2D::add_layer` can draw into it.
+pub trait PrimitiveSink {
+    fn size(&self) -> (u32, u32);
+    fn clear(&mut self, color: [f32; 4]);
+    fn thick_line(&mut self, x1: i16, y1: i16, x2: i16, y2: i16, width: u8, color: [f32; 4]);
+    fn rect(&mut self, x0: i16, y0: i16, x1: i16, y1: i16, color: [f32; 4]);
+    fn present(&mut self);
 }
 
 impl<'a> PlotGUI<'a> {
@@ -56,22 +115,57 @@ impl<'a> PlotGUI<'a> {
 
     fn event_loop(task_queue: Arc<Mutex<VecDeque<GUITask>>>) {
         let sdl_context = sdl2::init().unwrap();
-        let mut plot_gui = PlotGUI{sdl_context, windows: HashMap::new()};
+        let mut plot_gui = PlotGUI{sdl_context, windows: HashMap::new(), handles: HashMap::new()};
 
         let mut events = plot_gui.sdl_context.event_pump().unwrap();
+        let mut frame_start = Instant::now();
 
         'main: loop {
             for event in events.poll_iter() {
                 match event {
                     Event::Quit { .. } => break 'main,
-                    Event::KeyDown { keycode: Some(Keycode::Escape), window_id, .. } 
+                    Event::KeyDown { keycode: Some(Keycode::Escape), window_id, .. }
                     | Event::Window { win_event: sdl2::event::WindowEvent::Close, window_id, .. } => {
                         if let Some(_) = plot_gui.windows.remove(&window_id) {
+                            plot_gui.handles.retain(|_, sdl_id| *sdl_id != window_id);
                             if plot_gui.windows.is_empty() {
                                 break 'main;
                             }
                         }
                     },
+                    Event::KeyDown { keycode: Some(Keycode::R), window_id, .. } => {
+                        if let Some(window) = plot_gui.windows.get_mut(&window_id) {
+                            window.reset_camera();
+                        }
+                    },
+                    Event::MouseMotion { window_id, mousestate, x, y, xrel, yrel, .. } => {
+                        if let Some(window) = plot_gui.windows.get_mut(&window_id) {
+                            window.last_mouse_pos = (x, y);
+                            if mousestate.left() {
+                                window.pan(xrel, yrel);
+                            }
+                        }
+                    },
+                    Event::MouseWheel { window_id, y: scroll_y, .. } => {
+                        if let Some(window) = plot_gui.windows.get_mut(&window_id) {
+                            window.zoom(scroll_y);
+                        }
+                    },
+                    Event::Window { win_event: sdl2::event::WindowEvent::Resized(..), window_id, .. }
+                    | Event::Window { win_event: sdl2::event::WindowEvent::SizeChanged(..), window_id, .. } => {
+                        if let Some(window) = plot_gui.windows.get_mut(&window_id) {
+                            window.dirty = true;
+                        }
+                    },
+                    Event::Window { win_event: sdl2::event::WindowEvent::Exposed, window_id, .. } => {
+                        if let Some(window) = plot_gui.windows.get_mut(&window_id) {
+                            // Cheaply re-show the last rendered frame, unless a resize already
+                            // marked it dirty and it's about to be re-rasterized below anyway.
+                            if !window.dirty {
+                                window.present_last_frame();
+                            }
+                        }
+                    },
                     _ => {}
                 }
             }
@@ -79,8 +173,10 @@ impl<'a> PlotGUI<'a> {
                 match task_queue.lock().unwrap().pop_front() {
                     Some(gui_task) => {
                         match gui_task {
-                            GUITask::PlotTask(plot_builder, plot) => plot_gui.add_window(plot_builder,
-                                                                                         plot),
+                            GUITask::PlotTask(handle, plot_builder, plot) => plot_gui.add_window(handle,
+                                                                                                  plot_builder,
+                                                                                                  plot),
+                            GUITask::UpdateTask(handle, pvs) => plot_gui.update_window(handle, pvs),
                             GUITask::Terminate  => if plot_gui.windows.is_empty() {break 'main},
                         }
                     }
@@ -88,14 +184,24 @@ impl<'a> PlotGUI<'a> {
                 }
             }
 
+            // Only re-rasterize windows whose data, camera or size actually changed.
             for window in plot_gui.windows.values_mut() {
-                window.draw();
+                if window.dirty {
+                    window.draw();
+                    window.dirty = false;
+                }
             }
-            thread::sleep(Duration::from_millis(500));
-        }       
+
+            let target_frame = Duration::from_millis(16);
+            let elapsed = frame_start.elapsed();
+            if elapsed < target_frame {
+                thread::sleep(target_frame - elapsed);
+            }
+            frame_start = Instant::now();
+        }
     }
 
-    fn add_window(&mut self, plot_builder: PlotBuilder2D, plot: Plot) {
+    fn add_window(&mut self, handle: u32, plot_builder: PlotBuilder2D, plot: Plot) {
         let sdl_video = self.sdl_context.video().unwrap();
         let window = sdl_video.window("2D plot", 720, 720)
             .position_centered()
@@ -107,89 +213,230 @@ impl<'a> PlotGUI<'a> {
         let mut renderer = window.renderer().build().unwrap();
         let mut new_window = Window::new(id, renderer);
         plot.new2d(plot_builder, &mut new_window);
+        self.handles.insert(handle, id);
         self.windows.insert(id, new_window);
     }
+
+    fn update_window(&mut self, handle: u32, pvs: Vec<PlotVals2D>) {
+        if let Some(&id) = self.handles.get(&handle) {
+            if let Some(window) = self.windows.get_mut(&id) {
+                window.update(pvs);
+            }
+        }
+    }
 }
 
 impl<'a> Window<'a> {
     fn new(id: u32, renderer: Renderer<'a>) -> Window {
-        Window{id, renderer, xs: None, ys: None, colors: None, plot_bounds: None}
+        Window{id,
+               renderer,
+               xs: None,
+               ys: None,
+               colors: None,
+               plot_bounds: None,
+               camera: Camera2d{center: (0.0, 0.0), scale: (1.0, 1.0)},
+               last_mouse_pos: (0, 0),
+               dirty: true,
+               layers: default_layers()}
     }
 
     fn draw(&mut self) {
-        let xs = self.xs.as_ref().unwrap();
-        let ys = self.ys.as_ref().unwrap();
-        let plot_bounds = self.plot_bounds.as_ref().unwrap();
-        let colors = self.colors.as_ref().unwrap();
+        // Clone the stored data and take the layers out so `self` can be re-borrowed mutably
+        // as a `PrimitiveSink` by `render_plot` below.
+        let xs = self.xs.clone().unwrap();
+        let ys = self.ys.clone().unwrap();
+        let colors = self.colors.clone().unwrap();
+        let view_bounds = self.view_bounds();
+        let layers = mem::replace(&mut self.layers, Vec::new());
+
+        render_plot(self, &xs, &ys, &colors, view_bounds, &layers);
+
+        self.layers = layers;
+    }
+
+    // Re-shows the frame that's already in the renderer without recomputing any geometry;
+    // used when the window is merely exposed rather than actually changed.
+    fn present_last_frame(&mut self) {
+        self.renderer.present();
+    }
+
+    // The rectangle actually visible right now: the auto-fit bounds as seen through the camera.
+    fn view_bounds(&self) -> [f64; 4] {
+        self.camera.view_bounds(self.plot_bounds.unwrap())
+    }
+
+    fn reset_camera(&mut self) {
+        if let Some(plot_bounds) = self.plot_bounds {
+            self.camera = Camera2d::from_bounds(plot_bounds);
+            self.dirty = true;
+        }
+    }
 
-        let bordercol = f32_4_to_color([0.95, 0.95, 0.95, 1.0]);
-        let bgcol = f32_4_to_color([1.0, 1.0, 1.0, 1.0]);
+    // How much data space one pixel covers along each axis, at the current zoom level, using
+    // the same margin geometry `render_plot` draws with.
+    fn data_per_pixel(&self) -> (f64, f64) {
+        let view_bounds = self.view_bounds();
+        let (w, h) = self.renderer.output_size().unwrap();
         let margin = 0.05;
-        let invmargin = 1.0 - margin;
+        let m = min(w, h) as f64;
+        let space = m * margin;
+        let m = m * (1.0 - margin);
 
-        let x_max = plot_bounds[0];
-        let y_max = plot_bounds[1];
-        let x_min = plot_bounds[2];
-        let y_min = plot_bounds[3];
+        ((view_bounds[0] - view_bounds[2]) / (m - space),
+         (view_bounds[1] - view_bounds[3]) / (m - space))
+    }
 
-        let (mut w, mut h) = self.renderer.output_size().unwrap();
+    // Inverse of the `point2plot` mapping used by `render_plot`: turns a window-space pixel
+    // into the data-space point it currently shows.
+    fn mouse_to_data(&self, (px, py): (i32, i32)) -> (f64, f64) {
+        let view_bounds = self.view_bounds();
+        let x_max = view_bounds[0];
+        let y_max = view_bounds[1];
+        let x_min = view_bounds[2];
+        let y_min = view_bounds[3];
 
-        // println!("(w, h) = ({}, {})", w, h);
+        let (w, h) = self.renderer.output_size().unwrap();
+        let margin = 0.05;
         let m = min(w, h) as f64;
         let space = m * margin;
-        let m = m * invmargin;
+        let m = m * (1.0 - margin);
 
-        self.renderer.set_draw_color(bgcol);
-        self.renderer.clear();
-        draw_borders(bordercol, bgcol, space, m, &mut self.renderer);
-
-        let y0 = (m + space) as i16 - point2plot(0.0, y_min, y_max, m, space);
-        let xn = m;
-        // println!("xn: {}", xn);
-        self.renderer.thick_line(space as i16,
-                                 y0,
-                                 xn as i16,
-                                 y0,
-                                 2,
-                                 pixels::Color::RGBA(0, 0, 0, 255))
-                     .unwrap();
-
-        for i in 0..colors.len() {
-            let color = colors[i];
-            let color_rgba = f32_4_to_color(color);
-
-            let y_inv = (m + space) as i16;
-            let yt: Vec<i16> = ys[i].iter().map(|y| y_inv - point2plot(*y, y_min, y_max, m, space)).collect();
-            let xt: Vec<i16> = xs[i].iter().map(|x| point2plot(*x, x_min, x_max, m, space)).collect();
-
-            // The number of points
-            let len = xs[i].len();
-            for i in 0..len - 1 {
-                let (xa, ya) = (xt[i + 0], yt[i + 0]);
-                let (xb, yb) = (xt[i + 1], yt[i + 1]);
-                self.renderer.thick_line(xa, ya, xb, yb, 2, color_rgba).unwrap();
+        let data_x = x_min + ((px as f64 - space) / (m - space)) * (x_max - x_min);
+        let y_flipped = (m + space) - py as f64;
+        let data_y = y_min + ((y_flipped - space) / (m - space)) * (y_max - y_min);
+        (data_x, data_y)
+    }
+
+    // Translates the camera by a dragged pixel delta, converted to data units.
+    fn pan(&mut self, xrel: i32, yrel: i32) {
+        if self.plot_bounds.is_none() {
+            return;
+        }
+        let (x_scale, y_scale) = self.data_per_pixel();
+        self.camera.center.0 -= xrel as f64 * x_scale;
+        // Window y grows downward while data y grows upward, so the signs are flipped relative to x.
+        self.camera.center.1 += yrel as f64 * y_scale;
+        self.dirty = true;
+    }
+
+    // Scales the camera around the cursor's data-space position, so the point under the
+    // cursor stays fixed.
+    fn zoom(&mut self, scroll_y: i32) {
+        if self.plot_bounds.is_none() || scroll_y == 0 {
+            return;
+        }
+        let zoom_factor = if scroll_y > 0 { 1.1 } else { 1.0 / 1.1 };
+
+        let before = self.mouse_to_data(self.last_mouse_pos);
+        self.camera.scale.0 *= zoom_factor;
+        self.camera.scale.1 *= zoom_factor;
+        let after = self.mouse_to_data(self.last_mouse_pos);
+
+        self.camera.center.0 += before.0 - after.0;
+        self.camera.center.1 += before.1 - after.1;
+        self.dirty = true;
+    }
+
+    // Appends new samples onto the existing series (matched by index) and folds their
+    // extrema into the existing `plot_bounds` instead of rescanning every point.
+    fn update(&mut self, new_pvs: Vec<PlotVals2D>) {
+        let xs = self.xs.get_or_insert_with(Vec::new);
+        let ys = self.ys.get_or_insert_with(Vec::new);
+        let colors = self.colors.get_or_insert_with(Vec::new);
+        let mut plot_bounds = self.plot_bounds.unwrap_or([f64::MIN, f64::MIN, f64::MAX, f64::MAX]);
+
+        for (i, pv) in new_pvs.into_iter().enumerate() {
+            if let PlotVals2D::XyColor(color, xy) = pv {
+                while xs.len() <= i {
+                    xs.push(Vec::new());
+                    ys.push(Vec::new());
+                    colors.push(color);
+                }
+                for (x, y) in xy {
+                    plot_bounds[0] = plot_bounds[0].max(x);
+                    plot_bounds[1] = plot_bounds[1].max(y);
+                    plot_bounds[2] = plot_bounds[2].min(x);
+                    plot_bounds[3] = plot_bounds[3].min(y);
+                    xs[i].push(x);
+                    ys[i].push(y);
+                }
             }
         }
-        self.renderer.present();
+
+        self.plot_bounds = Some(plot_bounds);
+        self.dirty = true;
     }
 }
 
 impl<'a> Surface for Window<'a> {
-    fn draw_plots(&mut self, xs: Vec<Vec<f64>>, ys: Vec<Vec<f64>>, colors: Vec<[f32; 4]>, plot_bounds: [f64; 4]) {
+    fn draw_plots(&mut self, xs: Vec<Vec<f64>>, ys: Vec<Vec<f64>>, colors: Vec<[f32; 4]>, plot_bounds: [f64; 4],
+                  custom_layers: Vec<Box<Layer>>) {
         self.xs = Some(xs);
         self.ys = Some(ys);
         self.colors = Some(colors);
         self.plot_bounds = Some(plot_bounds);
+        self.camera = Camera2d::from_bounds(plot_bounds);
+        self.layers.extend(custom_layers);
         self.draw();
+        self.dirty = false;
+    }
+}
+
+impl<'a> PrimitiveSink for Window<'a> {
+    fn size(&self) -> (u32, u32) {
+        self.renderer.output_size().unwrap()
+    }
+
+    fn clear(&mut self, color: [f32; 4]) {
+        self.renderer.set_draw_color(f32_4_to_color(color));
+        self.renderer.clear();
+    }
+
+    fn thick_line(&mut self, x1: i16, y1: i16, x2: i16, y2: i16, width: u8, color: [f32; 4]) {
+        self.renderer.thick_line(x1, y1, x2, y2, width, f32_4_to_color(color)).unwrap();
+    }
+
+    fn rect(&mut self, x0: i16, y0: i16, x1: i16, y1: i16, color: [f32; 4]) {
+        self.renderer.rectangle(x0, y0, x1, y1, f32_4_to_color(color)).unwrap();
+    }
+
+    fn present(&mut self) {
+        self.renderer.present();
     }
 }
 
+/// `render_plot` is the backend-agnostic drawing routine shared by every `PrimitiveSink`:
+/// it composites `layers` in z-order into a `DrawCtx` built from the series data and the
+/// bounds currently visible, then presents the result. `Window` (SDL), `Frame` (in-memory
+/// buffer, for `Plotter::save2d`) and `TermSurface` all drive it instead of duplicating this
+/// geometry.
+pub(crate) fn render_plot(sink: &mut PrimitiveSink,
+                xs: &Vec<Vec<f64>>,
+                ys: &Vec<Vec<f64>>,
+                colors: &Vec<[f32; 4]>,
+                plot_bounds: [f64; 4],
+                layers: &Vec<Box<Layer>>) {
+    let ctx = DrawCtx {
+        xs,
+        ys,
+        colors,
+        view_bounds: plot_bounds,
+        size: sink.size(),
+    };
+
+    for layer in layers {
+        layer.render(sink, &ctx);
+    }
+
+    sink.present();
+}
+
 // pt: a point on a 1 dimensional line segment
 // min: the closest point to render on the line segment
 // max: the farthest point to render on the line segment
 // length: the length of the 1 dimensional window space
 // space: the offset from the beginning of the line segment
-fn point2plot(pt: f64, min: f64, max: f64, length: f64, space: f64) -> i16 {
+pub(crate) fn point2plot(pt: f64, min: f64, max: f64, length: f64, space: f64) -> i16 {
     (((pt - min) / (max - min)) * (length - space) + space) as i16
 }
 
@@ -228,25 +475,6 @@ fn f32_4_to_color(col: [f32; 4]) -> pixels::Color {
                         (col[3] * 255f32) as u8)
 }
 
-fn draw_borders(bordercol: pixels::Color, bgcol: pixels::Color, space: f64, m: f64, renderer: &mut Renderer) {
-    renderer.set_draw_color(bordercol);
-    renderer.clear();
-
-    renderer.rectangle((space - 1.0) as i16,
-                   (space - 1.0) as i16,
-                   (m - 1.0) as i16,
-                   (m - 1.0) as i16,
-                   pixels::Color::RGBA(0, 0, 255, 255))
-        .unwrap();
-
-    renderer.rectangle((space + 1.0) as i16,
-                   (space + 1.0) as i16,
-                   (m + 1.0) as i16,
-                   (m + 1.0) as i16,
-                   bgcol)
-        .unwrap();
-}
-
 fn set_xy(xy: &Vec<(f64, f64)>, x_vector: &mut Vec<Vec<f64>>, y_vector: &mut Vec<Vec<f64>>) {
     x_vector.push(Vec::new());
     y_vector.push(Vec::new());
@@ -294,8 +522,10 @@ impl Plot {
         let mut plot_builder = plot_builder;
 
         let mut pvs = Vec::new();
+        let mut custom_layers = Vec::new();
 
         mem::swap(&mut plot_builder.pvs, &mut pvs);
+        mem::swap(&mut plot_builder.custom_layers, &mut custom_layers);
 
         let mut colors: Vec<[f32; 4]> = Vec::new();
         let mut x_points: Vec<Vec<f64>> = Vec::new();
@@ -313,6 +543,6 @@ impl Plot {
 
         // [MAX_X, MAX_Y, MIN_X, MIN_Y]
         let plot_bounds: [f64; 4] = get_plot_bounds(&plot_builder, &x_points, &y_points);
-        surface.draw_plots(x_points, y_points, colors, plot_bounds);
+        surface.draw_plots(x_points, y_points, colors, plot_bounds, custom_layers);
     }
 }
@@ -0,0 +1,42 @@
+//! **plotbuilder** collects the data and options for a single plot before it's handed to
+//! `Plotter`: `PlotBuilder2D` accumulates series via `add_simple_xy`, and `PlotVals2D` is the
+//! per-series payload it queues (and that `Plotter::update2d` streams in afterwards).
+
+use layer::Layer;
+
+/// One series' color and `(x, y)` points, as queued onto a `PlotBuilder2D` or streamed in via
+/// `Plotter::update2d`.
+pub enum PlotVals2D {
+    XyColor([f32; 4], Vec<(f64, f64)>),
+}
+
+/// Accumulates the series and axis bounds for a single 2D plot, built up via `add_simple_xy`
+/// before being handed to `Plotter::plot2d`, `save2d` or `plot2d_term`.
+pub struct PlotBuilder2D {
+    pub(crate) pvs: Vec<PlotVals2D>,
+    pub(crate) max_x: Option<f64>,
+    pub(crate) max_y: Option<f64>,
+    pub(crate) min_x: Option<f64>,
+    pub(crate) min_y: Option<f64>,
+    // Overlays registered via `add_layer`, composited on top of `layer::default_layers` in
+    // z-order when this plot is drawn.
+    pub(crate) custom_layers: Vec<Box<Layer>>,
+}
+
+impl PlotBuilder2D {
+    pub fn new() -> PlotBuilder2D {
+        PlotBuilder2D{pvs: Vec::new(), max_x: None, max_y: None, min_x: None, min_y: None,
+                      custom_layers: Vec::new()}
+    }
+
+    /// Queues one series of `(x, y)` points, colored blue.
+    pub fn add_simple_xy(&mut self, xy: Vec<(f64, f64)>) {
+        self.pvs.push(PlotVals2D::XyColor([0.0, 0.0, 1.0, 1.0], xy));
+    }
+
+    /// Registers a custom overlay `Layer` (tick marks, annotations, ...), drawn on top of the
+    /// default background/grid/axis/series/legend stack when this plot is drawn.
+    pub fn add_layer(&mut self, layer: Box<Layer>) {
+        self.custom_layers.push(layer);
+    }
+}
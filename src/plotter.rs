@@ -9,11 +9,15 @@ use std::thread;
 use std::sync::Mutex;
 use std::sync::Arc;
 use std::collections::VecDeque;
+use std::path::Path;
 
 use plotbuilder::PlotBuilder2D;
+use plotbuilder::PlotVals2D;
 use plot::Plot;
 use plot::GUITask;
 use plot::PlotGUI;
+use frame::Frame;
+use term::TermSurface;
 
 use std::sync::atomic::{AtomicBool, ATOMIC_BOOL_INIT, Ordering};
 static GUI_INITIALISED: AtomicBool = ATOMIC_BOOL_INIT;
@@ -21,6 +25,7 @@ static GUI_INITIALISED: AtomicBool = ATOMIC_BOOL_INIT;
 pub struct Plotter {
     plot_gui: Option<thread::JoinHandle<()>>,
     task_queue: Arc<Mutex<VecDeque<GUITask>>>,
+    next_window_id: u32,
 }
 
 impl Plotter {
@@ -33,23 +38,55 @@ impl Plotter {
         }
         else {
             GUI_INITIALISED.store(true, Ordering::Relaxed);
-            Ok(Plotter { 
+            Ok(Plotter {
                 plot_gui: Some(PlotGUI::run(task_queue.clone())),
                 task_queue,
+                next_window_id: 0,
             })
         }
     }
 
-    /// `plot2d` is currently the only supported plotting function. It takes a `PlotBuilder2D` containing all needed information.
-    pub fn plot2d(&mut self, plot_builder: PlotBuilder2D) {
-        let plot_task = GUITask::PlotTask(plot_builder, Plot::new());
+    /// `plot2d` takes a `PlotBuilder2D` containing all needed information and opens it in a
+    /// new window, returning a window id that can later be passed to `update2d` to stream
+    /// further data into it.
+    pub fn plot2d(&mut self, plot_builder: PlotBuilder2D) -> u32 {
+        let window_id = self.next_window_id;
+        self.next_window_id += 1;
+
+        let plot_task = GUITask::PlotTask(window_id, plot_builder, Plot::new());
         self.task_queue.lock().unwrap().push_back(plot_task);
+        window_id
     }
 
-}
+    /// `update2d` appends new data onto an already-open plot, identified by the window id
+    /// `plot2d` returned when it was created. This is how a caller streams data (e.g. from a
+    /// sensor loop) into a live plot instead of only creating new ones.
+    pub fn update2d(&mut self, window_id: u32, pvs: Vec<PlotVals2D>) {
+        let update_task = GUITask::UpdateTask(window_id, pvs);
+        self.task_queue.lock().unwrap().push_back(update_task);
+    }
 
-impl Drop for Plotter {
-    fn drop(&mut self) {
+    /// `save2d` renders a plot offscreen into a `(w, h)` `Frame` and writes it to `path` as a
+    /// PNG, without opening a window. This is the headless/CI counterpart to `plot2d`.
+    pub fn save2d(&mut self, plot_builder: PlotBuilder2D, path: &str, (w, h): (u32, u32)) -> Result<(), String> {
+        let mut frame = Frame::new(w, h);
+        Plot::new().new2d(plot_builder, &mut frame);
+        frame.save_png(Path::new(path))
+    }
+
+    /// `plot2d_term` renders a plot once directly to stdout as Unicode Braille characters in
+    /// a `(cols, rows)` character grid, for headless/SSH sessions where no window server is
+    /// available.
+    pub fn plot2d_term(&mut self, plot_builder: PlotBuilder2D, (cols, rows): (usize, usize)) {
+        let mut term = TermSurface::new(cols, rows);
+        Plot::new().new2d(plot_builder, &mut term);
+    }
+
+    /// `join` blocks until the user has closed every plot window opened through this
+    /// `Plotter`, then shuts down its background GUI thread. A `Plotter` also does this
+    /// automatically when dropped; call `join` directly when the caller wants to wait without
+    /// ending the `Plotter`'s scope.
+    pub fn join(&mut self) {
         if let Some(plot_gui) = self.plot_gui.take() {
             // terminate the gui thread if there are no plots open and wait for it
             self.task_queue.lock().unwrap().push_back(GUITask::Terminate);
@@ -57,6 +94,13 @@ impl Drop for Plotter {
         }
         GUI_INITIALISED.store(false, Ordering::Relaxed);
     }
+
+}
+
+impl Drop for Plotter {
+    fn drop(&mut self) {
+        self.join();
+    }
 }
 
 #[cfg(test)]
@@ -74,8 +118,51 @@ mod test {
 
         let mut pb1 = PlotBuilder2D::new();
         pb1.add_simple_xy(xy);
-        let mut plt = Plotter::new();
+        let mut plt = Plotter::new().unwrap();
         plt.plot2d(pb1);
         plt.join();
     }
+
+    #[test]
+    fn update2d_test() {
+
+        let x = linspace(0, 10, 100);
+        let y = (&x).iter().map(|x| x.sin()).collect();
+        let xy = zip2(&x, &y);
+
+        let mut pb1 = PlotBuilder2D::new();
+        pb1.add_simple_xy(xy);
+        let mut plt = Plotter::new().unwrap();
+        let window_id = plt.plot2d(pb1);
+
+        let more_xy = zip2(&linspace(10, 11, 10), &vec![0.0; 10]);
+        plt.update2d(window_id, vec![PlotVals2D::XyColor([0.0, 0.0, 0.0, 1.0], more_xy)]);
+        plt.join();
+    }
+
+    #[test]
+    fn save2d_test() {
+
+        let x = linspace(0, 10, 100);
+        let y = (&x).iter().map(|x| x.sin()).collect();
+        let xy = zip2(&x, &y);
+
+        let mut pb1 = PlotBuilder2D::new();
+        pb1.add_simple_xy(xy);
+        let mut plt = Plotter::new().unwrap();
+        plt.save2d(pb1, "/tmp/dataplotlib_save2d_test.png", (256, 256)).unwrap();
+    }
+
+    #[test]
+    fn plot2d_term_test() {
+
+        let x = linspace(0, 10, 100);
+        let y = (&x).iter().map(|x| x.sin()).collect();
+        let xy = zip2(&x, &y);
+
+        let mut pb1 = PlotBuilder2D::new();
+        pb1.add_simple_xy(xy);
+        let mut plt = Plotter::new().unwrap();
+        plt.plot2d_term(pb1, (80, 24));
+    }
 }
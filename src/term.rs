@@ -0,0 +1,148 @@
+//! **term** is a headless `Surface` backend: it rasterizes a plot into a character grid of
+//! Unicode Braille cells instead of driving an SDL window, so the crate can draw in
+//! terminals and CI logs where no window server exists.
+//!
+//! Users of **dataplotlib** should not need to access **term** directly; see
+//! `Plotter::plot2d_term`.
+
+use std::char;
+
+use plot::{PrimitiveSink, Surface, render_plot};
+use layer::{Layer, default_layers};
+
+// Each Braille cell covers a 2x4 sub-pixel dot pattern; these are the Unicode Braille
+// Patterns dot bits for (row, col) within a cell.
+const BRAILLE_DOTS: [[u8; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+/// `TermSurface` rasterizes a plot into a `cols x rows` grid of Braille cells, each covering
+/// a 2x4 sub-pixel block, and maps series colors onto ANSI truecolor escapes.
+pub struct TermSurface {
+    cols: usize,
+    rows: usize,
+    dots: Vec<u8>,
+    // The color of the last dot set in each cell; `None` for an untouched cell.
+    colors: Vec<Option<[f32; 4]>>,
+}
+
+impl TermSurface {
+    pub fn new(cols: usize, rows: usize) -> TermSurface {
+        TermSurface {
+            cols,
+            rows,
+            dots: vec![0; cols * rows],
+            colors: vec![None; cols * rows],
+        }
+    }
+
+    fn set_subpixel(&mut self, x: i32, y: i32, color: [f32; 4]) {
+        if x < 0 || y < 0 {
+            return;
+        }
+        let (cell_x, sub_x) = ((x / 2) as usize, (x % 2) as usize);
+        let (cell_y, sub_y) = ((y / 4) as usize, (y % 4) as usize);
+        if cell_x >= self.cols || cell_y >= self.rows {
+            return;
+        }
+
+        let idx = cell_y * self.cols + cell_x;
+        self.dots[idx] |= BRAILLE_DOTS[sub_y][sub_x];
+        self.colors[idx] = Some(color);
+    }
+
+    // Bresenham's line algorithm over sub-pixels, mirroring the `thick_line` calls
+    // `Window::draw` makes against the SDL renderer.
+    fn line(&mut self, x1: i32, y1: i32, x2: i32, y2: i32, color: [f32; 4]) {
+        let (mut x1, mut y1) = (x1, y1);
+        let dx = (x2 - x1).abs();
+        let dy = -(y2 - y1).abs();
+        let sx = if x1 < x2 { 1 } else { -1 };
+        let sy = if y1 < y2 { 1 } else { -1 };
+        let mut err = dx + dy;
+
+        loop {
+            self.set_subpixel(x1, y1, color);
+            if x1 == x2 && y1 == y2 {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x1 += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y1 += sy;
+            }
+        }
+    }
+
+    // Rectangle outline, mirroring the `rectangle` calls `Window::draw` makes.
+    fn rect_px(&mut self, x0: i32, y0: i32, x1: i32, y1: i32, color: [f32; 4]) {
+        self.line(x0, y0, x1, y0, color);
+        self.line(x1, y0, x1, y1, color);
+        self.line(x1, y1, x0, y1, color);
+        self.line(x0, y1, x0, y0, color);
+    }
+
+    /// Renders the buffer to a `String` of Braille characters, one line per row, each dot
+    /// cell wrapped in the ANSI truecolor escape of the last color drawn into it (a series,
+    /// or the border, grid, axis or legend).
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                let idx = row * self.cols + col;
+                let ch = char::from_u32(0x2800 + self.dots[idx] as u32).unwrap();
+                match self.colors[idx] {
+                    Some(color) => {
+                        let (r, g, b) = ansi_rgb(color);
+                        out.push_str(&format!("\x1b[38;2;{};{};{}m{}\x1b[0m", r, g, b, ch));
+                    }
+                    None => out.push(ch),
+                }
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+fn ansi_rgb(col: [f32; 4]) -> (u8, u8, u8) {
+    ((col[0] * 255f32) as u8, (col[1] * 255f32) as u8, (col[2] * 255f32) as u8)
+}
+
+impl PrimitiveSink for TermSurface {
+    fn size(&self) -> (u32, u32) {
+        (self.cols as u32 * 2, self.rows as u32 * 4)
+    }
+
+    fn clear(&mut self, _color: [f32; 4]) {
+        for dot in self.dots.iter_mut() {
+            *dot = 0;
+        }
+        for color in self.colors.iter_mut() {
+            *color = None;
+        }
+    }
+
+    fn thick_line(&mut self, x1: i16, y1: i16, x2: i16, y2: i16, _width: u8, color: [f32; 4]) {
+        self.line(x1 as i32, y1 as i32, x2 as i32, y2 as i32, color);
+    }
+
+    fn rect(&mut self, x0: i16, y0: i16, x1: i16, y1: i16, color: [f32; 4]) {
+        self.rect_px(x0 as i32, y0 as i32, x1 as i32, y1 as i32, color);
+    }
+
+    fn present(&mut self) {
+        print!("{}", self.render());
+    }
+}
+
+impl Surface for TermSurface {
+    fn draw_plots(&mut self, xs: Vec<Vec<f64>>, ys: Vec<Vec<f64>>, colors: Vec<[f32; 4]>, plot_bounds: [f64; 4],
+                  custom_layers: Vec<Box<Layer>>) {
+        let mut layers = default_layers();
+        layers.extend(custom_layers);
+        render_plot(self, &xs, &ys, &colors, plot_bounds, &layers);
+    }
+}
@@ -0,0 +1,20 @@
+//! **util** is a small grab-bag of helpers for building example and test data: `linspace` for
+//! an evenly spaced range and `zip2` for pairing two equal-length series into `(x, y)` points.
+
+/// Returns `n` evenly spaced values from `start` to `end`, inclusive.
+pub fn linspace<T: Into<f64>>(start: T, end: T, n: usize) -> Vec<f64> {
+    let (start, end): (f64, f64) = (start.into(), end.into());
+    if n == 0 {
+        return Vec::new();
+    }
+    if n == 1 {
+        return vec![start];
+    }
+    let step = (end - start) / (n - 1) as f64;
+    (0..n).map(|i| start + step * i as f64).collect()
+}
+
+/// Pairs two equal-length series into `(x, y)` points.
+pub fn zip2(xs: &Vec<f64>, ys: &Vec<f64>) -> Vec<(f64, f64)> {
+    xs.iter().cloned().zip(ys.iter().cloned()).collect()
+}